@@ -0,0 +1,313 @@
+//! GPU-backed Life step, used by `Grid::update_cells` as a faster
+//! alternative to the CPU triple-nested loop once `Grid::enable_gpu` is
+//! called. Tracks aliveness only (one `u32` per cell); the caller is
+//! responsible for re-deriving the richer, fading `Cell` state from the
+//! result when it actually needs it (e.g. after `Grid::disable_gpu`).
+//!
+//! The aliveness buffer lives on the device for as long as the simulator
+//! does: `step` advances it in place without re-uploading the grid, and
+//! `upload`/`download` are the only points that cross the CPU/GPU boundary,
+//! so a caller that just wants to keep stepping never pays for either.
+
+use std::borrow::Cow;
+use std::sync::mpsc;
+
+use pixels::wgpu;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+const SHADER_SRC: &str = r#"
+struct GridParams {
+    width: u32,
+    height: u32,
+    // Non-zero when edges should wrap (BoundaryMode::Toroidal); zero means
+    // out-of-bounds neighbours read as dead (BoundaryMode::Bounded).
+    wrap: u32,
+    // Bit `n` set means a dead cell with `n` live neighbours is born.
+    birth_mask: u32,
+    // Bit `n` set means a live cell with `n` live neighbours survives.
+    survival_mask: u32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: GridParams;
+@group(0) @binding(1) var<storage, read> current: array<u32>;
+@group(0) @binding(2) var<storage, read_write> next: array<u32>;
+
+fn is_alive(x: i32, y: i32) -> u32 {
+    var nx = x;
+    var ny = y;
+
+    if (params.wrap != 0u) {
+        nx = (x + i32(params.width)) % i32(params.width);
+        ny = (y + i32(params.height)) % i32(params.height);
+    } else if (x < 0 || y < 0 || x >= i32(params.width) || y >= i32(params.height)) {
+        return 0u;
+    }
+
+    return current[u32(ny) * params.width + u32(nx)];
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+
+    let x = i32(id.x);
+    let y = i32(id.y);
+
+    var live_neighbours: u32 = 0u;
+    live_neighbours += is_alive(x - 1, y - 1);
+    live_neighbours += is_alive(x, y - 1);
+    live_neighbours += is_alive(x + 1, y - 1);
+    live_neighbours += is_alive(x - 1, y);
+    live_neighbours += is_alive(x + 1, y);
+    live_neighbours += is_alive(x - 1, y + 1);
+    live_neighbours += is_alive(x, y + 1);
+    live_neighbours += is_alive(x + 1, y + 1);
+
+    let index = id.y * params.width + id.x;
+    let was_alive = current[index] == 1u;
+    let mask = select(params.birth_mask, params.survival_mask, was_alive);
+    let alive_next = ((mask >> live_neighbours) & 1u) == 1u;
+    next[index] = select(0u, 1u, alive_next);
+}
+"#;
+
+fn pack_u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+fn unpack_u32s(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+/// Runs the Life step described by `wrap`/`birth_mask`/`survival_mask` via a
+/// ping-pong pair of storage buffers that stay resident on the device across
+/// calls to `step` - the grid is only uploaded once (`upload`, at
+/// `Grid::enable_gpu`/resize time) and only downloaded when the caller asks
+/// for it (`download`), not on every generation.
+pub struct GpuSimulator {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    params_buffer: wgpu::Buffer,
+    front_buffer: wgpu::Buffer,
+    back_buffer: wgpu::Buffer,
+    staging_buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+}
+
+impl GpuSimulator {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        wrap: bool,
+        birth_mask: u32,
+        survival_mask: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("life-step-shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SRC)),
+        });
+
+        let buffer_size = (width as u64) * (height as u64) * 4;
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("life-params"),
+            size: 32,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let make_storage_buffer = |label: &str| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: buffer_size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+
+        let front_buffer = make_storage_buffer("life-front");
+        let back_buffer = make_storage_buffer("life-back");
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("life-staging"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("life-step-layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("life-step-pipeline-layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("life-step-pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        let params = [width, height, wrap as u32, birth_mask, survival_mask, 0, 0, 0];
+        queue.write_buffer(&params_buffer, 0, &pack_u32s(&params));
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            params_buffer,
+            front_buffer,
+            back_buffer,
+            staging_buffer,
+            width,
+            height,
+        }
+    }
+
+    fn bind_group(&self, device: &wgpu::Device) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("life-step-bind-group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.front_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.back_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Uploads `alive` (one `u32` per cell, row-major) into the resident
+    /// front buffer. Called once when GPU mode is enabled (or the grid is
+    /// resized), not on every step.
+    pub fn upload(&self, queue: &wgpu::Queue, alive: &[u32]) {
+        queue.write_buffer(&self.front_buffer, 0, &pack_u32s(alive));
+    }
+
+    /// Writes a single cell into the resident front buffer, so painting with
+    /// the mouse takes effect while GPU mode is on; the device never reads
+    /// `Grid::cells` between uploads, so an edit made only there is not seen.
+    pub fn write_cell(&self, queue: &wgpu::Queue, x: u32, y: u32, alive: bool) {
+        let offset = ((y * self.width + x) as u64) * 4;
+        queue.write_buffer(&self.front_buffer, offset, &(alive as u32).to_ne_bytes());
+    }
+
+    /// Reads the current aliveness (one `u32` per cell, row-major) back from
+    /// the device. This is the only way to observe the simulation's state
+    /// from the CPU side, so callers that don't need it every frame
+    /// shouldn't call it every frame.
+    pub fn download(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<u32> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("life-download-encoder"),
+        });
+        encoder.copy_buffer_to_buffer(
+            &self.front_buffer,
+            0,
+            &self.staging_buffer,
+            0,
+            self.staging_buffer.size(),
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = self.staging_buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .expect("GPU readback channel closed before the map completed")
+            .expect("failed to map the staging buffer for readback");
+
+        let alive = unpack_u32s(&slice.get_mapped_range());
+        drop(slice);
+        self.staging_buffer.unmap();
+
+        alive
+    }
+
+    /// Advances the resident aliveness buffer by one generation on the
+    /// device. Doesn't touch the CPU at all - no upload, no readback - so
+    /// repeated calls are just GPU dispatches, however large the grid.
+    pub fn step(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let bind_group = self.bind_group(device);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("life-step-encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("life-step-pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(
+                (self.width + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                (self.height + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                1,
+            );
+        }
+        queue.submit(Some(encoder.finish()));
+
+        std::mem::swap(&mut self.front_buffer, &mut self.back_buffer);
+    }
+}
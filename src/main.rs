@@ -1,80 +1,455 @@
-use pixels::{wgpu::Color, Error, Pixels, SurfaceTexture};
-use winit::{dpi::LogicalSize, event_loop::EventLoop, window::WindowBuilder};
+mod gpu;
+
+use gpu::GpuSimulator;
+use pixels::{wgpu, wgpu::Color, Error, Pixels, SurfaceTexture};
+use winit::{
+    dpi::LogicalSize,
+    event::{Event, VirtualKeyCode},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
 use winit_input_helper::WinitInputHelper;
 
-const WIDTH: i32 = 500;
-const HEIGHT: i32 = 300;
+#[cfg(target_arch = "wasm32")]
+use winit::platform::web::WindowExtWebSys;
+
+const INITIAL_WIDTH: i32 = 500;
+const INITIAL_HEIGHT: i32 = 300;
 const SCALE_FACTOR: f64 = 10.0;
 
-#[derive(Clone, Debug)]
-struct Cell {
-    pub is_alive: bool,
+// How long (in generations) a dead cell keeps fading before it's
+// indistinguishable from the background.
+const MAX_FADE: u8 = 32;
+// The RGB a cell flashes the instant it dies, before fading to black.
+const DEATH_FLASH: [u8; 3] = [0xff, 0x40, 0x00];
+
+#[derive(Clone, Copy, Debug)]
+enum Cell {
+    Alive,
+    // `since` counts generations since the cell died, saturating at
+    // `MAX_FADE` so long-dead regions settle to a stable black.
+    Dead { since: u8 },
 }
 
 impl Cell {
-    // Leveraging Rust's powerfull Options
-    // by assuming that if the .get() on a Grid
-    // is None, then we are out of bound.
-    // This can be represented by a neighbouring dead
-    // cell.
-    // Although probably memory heavy, since we are
-    // creating an instance each time..
-    // todo make proper rule check
+    // Used to fill the halo/padding around the live grid, fully faded
+    // so it renders as plain background.
     fn dead_cell() -> Self {
-        Self { is_alive: false }
+        Cell::Dead { since: MAX_FADE }
     }
 
-    fn process_next_state(&self, neighbours: [bool; 8]) -> Self {
-        let n_count = neighbours.into_iter().filter(|b| *b).count();
-        let is_alive_next = match self.is_alive {
-            // If the cell is alive, then it stays alive if it has either 2 or 3 live neighbors
-            true => (2..=3).contains(&n_count),
+    fn is_alive(&self) -> bool {
+        matches!(self, Cell::Alive)
+    }
 
-            // If the cell is dead, then it springs to life only in the case that it has 3 live neighbors
-            false => n_count == 3,
+    fn process_next_state(&self, neighbours: [bool; 8], rule: &Rule) -> Self {
+        let n_count = neighbours.into_iter().filter(|b| *b).count();
+        let is_alive_next = match self {
+            Cell::Alive => rule.survival[n_count],
+            Cell::Dead { .. } => rule.birth[n_count],
         };
 
-        Self {
-            is_alive: is_alive_next,
+        if is_alive_next {
+            Cell::Alive
+        } else {
+            match self {
+                Cell::Alive => Cell::Dead { since: 0 },
+                Cell::Dead { since } => Cell::Dead {
+                    since: since.saturating_add(1).min(MAX_FADE),
+                },
+            }
         }
     }
 }
 
-
+// A Lifelike cellular automaton rule in "Bxxx/Syyy" notation: a cell is
+// born with `x` live neighbours, and an already-alive cell survives with
+// `y` live neighbours. `birth`/`survival` are indexed by neighbour count.
 #[derive(Clone, Debug)]
+struct Rule {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl Rule {
+    // Parses the standard "Bxxx/Syyy" notation, e.g. "B3/S23" (classic
+    // Life), "B36/S23" (HighLife), "B2/S" (Seeds).
+    fn parse(pattern: &str) -> Result<Self, String> {
+        let (b_part, s_part) = pattern
+            .split_once('/')
+            .ok_or_else(|| format!("rule {pattern:?} is missing the '/' separator"))?;
+
+        let b_digits = b_part
+            .strip_prefix('B')
+            .ok_or_else(|| format!("birth half {b_part:?} must start with 'B'"))?;
+        let s_digits = s_part
+            .strip_prefix('S')
+            .ok_or_else(|| format!("survival half {s_part:?} must start with 'S'"))?;
+
+        let mut birth = [false; 9];
+        let mut survival = [false; 9];
+        for (digits, table) in [(b_digits, &mut birth), (s_digits, &mut survival)] {
+            for digit in digits.chars() {
+                let n_count = digit
+                    .to_digit(10)
+                    .filter(|n| *n <= 8)
+                    .ok_or_else(|| format!("invalid neighbour count {digit:?} in {pattern:?}"))?;
+                table[n_count as usize] = true;
+            }
+        }
+
+        Ok(Self { birth, survival })
+    }
+
+    // Packs `birth`/`survival` into bitmasks (bit `n` set means neighbour
+    // count `n` applies), the form the GPU compute shader's uniforms want.
+    fn birth_mask(&self) -> u32 {
+        Self::pack_mask(&self.birth)
+    }
+
+    fn survival_mask(&self) -> u32 {
+        Self::pack_mask(&self.survival)
+    }
+
+    fn pack_mask(table: &[bool; 9]) -> u32 {
+        table
+            .iter()
+            .enumerate()
+            .fold(0u32, |mask, (n, &on)| if on { mask | (1 << n) } else { mask })
+    }
+}
+
+impl Default for Rule {
+    // The classic B3/S23 ruleset.
+    fn default() -> Self {
+        Self::parse("B3/S23").expect("default rule is valid Bxxx/Syyy notation")
+    }
+}
+
+
+// How the grid treats its edges when counting neighbours.
+#[derive(Clone, Copy, Debug)]
+enum BoundaryMode {
+    // The grid is surrounded by a one-cell-thick halo of cells that are
+    // always dead, so the top row and left column have real neighbours
+    // on every side instead of silently reading as "out of bounds".
+    Bounded,
+    // Opposite edges are stitched together with modular arithmetic, so a
+    // glider that exits the right edge re-enters on the left.
+    Toroidal,
+}
+
+impl BoundaryMode {
+    // Maps a logical grid coordinate to its index in `Grid::cells`. For
+    // `Bounded`, coordinates one step into the halo (-1 or width/height)
+    // are valid too, which is what lets `update_cells_cpu` skip bounds checks.
+    // Lives on `BoundaryMode` rather than `Grid` so it can be called while
+    // another field of `Grid` is mutably borrowed.
+    fn cell_index(self, x: i32, y: i32, width: i32) -> usize {
+        match self {
+            BoundaryMode::Bounded => ((x + 1) + (y + 1) * (width + 2)) as usize,
+            BoundaryMode::Toroidal => (x + y * width) as usize,
+        }
+    }
+
+    // Resolves the coordinate of the neighbour at offset `(dx, dy)` from
+    // `(x, y)`, wrapping around the grid for `Toroidal`.
+    fn neighbour_coords(self, x: i32, y: i32, dx: i32, dy: i32, width: i32, height: i32) -> (i32, i32) {
+        match self {
+            BoundaryMode::Bounded => (x + dx, y + dy),
+            BoundaryMode::Toroidal => ((x + dx + width) % width, (y + dy + height) % height),
+        }
+    }
+}
+
 struct Grid {
     pub cells: Vec<Cell>,
     pub next_step_cells: Vec<Cell>,
+    width: i32,
+    height: i32,
+    mode: BoundaryMode,
+    rule: Rule,
+    // `Some` once `enable_gpu` has run `update_cells` dispatches the Life
+    // step on the device instead of looping over `cells` on the CPU.
+    gpu_simulator: Option<GpuSimulator>,
+    // The last aliveness snapshot downloaded from `gpu_simulator`, used by
+    // `draw_cell` to render while in GPU mode without having to derive
+    // `cells`' fade state (or re-download it) on every frame.
+    gpu_alive_cache: Vec<u32>,
 }
 
 impl Grid {
-    fn get_randomized_grid() -> Self {
+    fn buffer_len(mode: BoundaryMode, width: i32, height: i32) -> usize {
+        match mode {
+            // +2 on each axis for the one-cell dead halo on every side.
+            BoundaryMode::Bounded => ((width + 2) * (height + 2)) as usize,
+            BoundaryMode::Toroidal => (width * height) as usize,
+        }
+    }
+
+    fn get_randomized_grid(width: i32, height: i32, mode: BoundaryMode, rule: Rule) -> Self {
         let mut rng: randomize::PCG32 = (1_u64, 1_u64).into();
 
-        let cells: Vec<Cell> = (0..(HEIGHT as usize * WIDTH as usize))
-            .map(|_| Cell {
-                is_alive: randomize::f32_half_open_right(rng.next_u32()) > 0.9,
-            })
-            .collect();
+        let buffer_len = Self::buffer_len(mode, width, height);
+
+        let mut grid = Self {
+            cells: vec![Cell::dead_cell(); buffer_len],
+            next_step_cells: vec![Cell::dead_cell(); buffer_len],
+            width,
+            height,
+            mode,
+            rule,
+            gpu_simulator: None,
+            gpu_alive_cache: Vec::new(),
+        };
+
+        for x in 0..width {
+            for y in 0..height {
+                if randomize::f32_half_open_right(rng.next_u32()) > 0.9 {
+                    grid.set_cell_alive(x, y);
+                }
+            }
+        }
+
+        grid
+    }
+
+    // Reallocates `cells`/`next_step_cells` for a new grid size, preserving
+    // the overlapping top-left region of the existing pattern and filling
+    // any newly exposed area with dead cells. Called when the window (and
+    // therefore the number of cells that fit in it) is resized.
+    fn resize(&mut self, new_width: i32, new_height: i32, device: &wgpu::Device, queue: &wgpu::Queue) {
+        // Bring the GPU's resident state back into `cells` before the
+        // buffers it lives in get reallocated below.
+        let gpu_was_enabled = self.gpu_simulator.is_some();
+        if gpu_was_enabled {
+            self.disable_gpu(device, queue);
+        }
+
+        let buffer_len = Self::buffer_len(self.mode, new_width, new_height);
+        let mut new_cells = vec![Cell::dead_cell(); buffer_len];
+
+        for x in 0..self.width.min(new_width) {
+            for y in 0..self.height.min(new_height) {
+                let old_id = self.mode.cell_index(x, y, self.width);
+                let new_id = self.mode.cell_index(x, y, new_width);
+                new_cells[new_id] = self.cells[old_id];
+            }
+        }
+
+        self.cells = new_cells;
+        self.next_step_cells = vec![Cell::dead_cell(); buffer_len];
+        self.width = new_width;
+        self.height = new_height;
+
+        if gpu_was_enabled {
+            self.enable_gpu(device, queue);
+        }
+    }
+
+    // Brings a single cell to life. Coordinates from the cursor can land
+    // right on (or past) the window edge, so out-of-bound ones are ignored.
+    fn set_cell_alive(&mut self, x: i32, y: i32) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+
+        let id = self.mode.cell_index(x, y, self.width);
+        self.cells[id] = Cell::Alive;
+    }
+
+    // Same as `set_cell_alive`, but also mirrors the write into the GPU's
+    // resident buffer (and `gpu_alive_cache`, so it shows up immediately in
+    // `draw_cell` instead of waiting for the next `update_cells`) if GPU
+    // mode is on. Always paints alive rather than toggling, so dragging the
+    // mouse across a line of cells (`draw_line`) doesn't flicker them on
+    // and off as the same cell is revisited frame to frame.
+    fn paint_cell_alive(&mut self, x: i32, y: i32, queue: &wgpu::Queue) {
+        self.set_cell_alive(x, y);
+
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        if self.gpu_simulator.is_some() {
+            self.write_gpu_cell(x, y, true, queue);
+        }
+    }
+
+    // Flips a single cell between alive and dead. Used for a plain
+    // left-click, where the user is toggling one cell at a time rather than
+    // painting a line.
+    fn toggle_cell(&mut self, x: i32, y: i32, queue: &wgpu::Queue) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+
+        let id = self.mode.cell_index(x, y, self.width);
+        let now_alive = !self.cells[id].is_alive();
+        self.cells[id] = if now_alive {
+            Cell::Alive
+        } else {
+            Cell::Dead { since: 0 }
+        };
+
+        if self.gpu_simulator.is_some() {
+            self.write_gpu_cell(x, y, now_alive, queue);
+        }
+    }
+
+    // Mirrors a single-cell edit into the GPU's resident buffer and into
+    // `gpu_alive_cache`, so a mouse edit while GPU mode is on renders
+    // immediately instead of only becoming visible on the next
+    // `update_cells` (which is what refreshes the cache otherwise).
+    fn write_gpu_cell(&mut self, x: i32, y: i32, alive: bool, queue: &wgpu::Queue) {
+        if let Some(gpu) = &self.gpu_simulator {
+            gpu.write_cell(queue, x as u32, y as u32, alive);
+        }
+        let idx = (y * self.width + x) as usize;
+        if let Some(slot) = self.gpu_alive_cache.get_mut(idx) {
+            *slot = alive as u32;
+        }
+    }
+
+    // Walks the integer grid between `from` and `to` with Bresenham's
+    // algorithm, so dragging the mouse draws a continuous line instead of
+    // leaving gaps when the cursor moves faster than one cell per frame.
+    fn draw_line(&mut self, from: (i32, i32), to: (i32, i32), queue: &wgpu::Queue) {
+        let (mut x, mut y) = from;
+        let (x1, y1) = to;
+
+        let dx = (x1 - x).abs();
+        let dy = -(y1 - y).abs();
+        let sx = if x < x1 { 1 } else { -1 };
+        let sy = if y < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
 
-            
-        let next_step_cells: Vec<Cell> = vec![Cell::dead_cell(); HEIGHT as usize * WIDTH as usize];
+        loop {
+            self.paint_cell_alive(x, y, queue);
 
-        Self {
-            cells,
-            next_step_cells,
+            if x == x1 && y == y1 {
+                break;
+            }
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
         }
     }
 
     fn draw_cell(&mut self, frame: &mut [u8]) {
-        for (cell, pixel) in self.cells.iter().zip(frame.chunks_exact_mut(4)) {
-            let color = if cell.is_alive {
-                [0xff, 0xff, 0xff, 0xff] // White
-            } else {
-                [0, 0, 0, 0] // Black
-            };
+        // In GPU mode `cells` isn't kept up to date every frame (see
+        // `update_cells`), so render straight from the cached aliveness
+        // instead - plain white/black, since the device doesn't track the
+        // per-cell fade age that makes the death-flash trail possible.
+        if self.gpu_simulator.is_some() {
+            for x in 0..self.width {
+                for y in 0..self.height {
+                    let idx = (y * self.width + x) as usize;
+                    let alive = self.gpu_alive_cache.get(idx).copied().unwrap_or(0) != 0;
+                    let color = if alive {
+                        [0xff, 0xff, 0xff, 0xff]
+                    } else {
+                        [0x00, 0x00, 0x00, 0xff]
+                    };
 
-            pixel.copy_from_slice(&color);
+                    let pixel_offset = idx * 4;
+                    frame[pixel_offset..pixel_offset + 4].copy_from_slice(&color);
+                }
+            }
+            return;
+        }
+
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let cell = &self.cells[self.mode.cell_index(x, y, self.width)];
+                let color = match cell {
+                    Cell::Alive => [0xff, 0xff, 0xff, 0xff], // White
+                    Cell::Dead { since } => {
+                        // Linearly fade the death-flash color toward black.
+                        let t = 1.0 - (*since as f32 / MAX_FADE as f32);
+                        let fade = |channel: u8| (channel as f32 * t) as u8;
+                        [
+                            fade(DEATH_FLASH[0]),
+                            fade(DEATH_FLASH[1]),
+                            fade(DEATH_FLASH[2]),
+                            0xff,
+                        ]
+                    }
+                };
+
+                let pixel_offset = ((x + y * self.width) * 4) as usize;
+                frame[pixel_offset..pixel_offset + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    // From top-left to bottom-right.
+    const NEIGHBOUR_OFFSETS: [(i32, i32); 8] = [
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+        (-1, 0),
+        (1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+    ];
+
+    // Advances the grid by one generation, using the GPU path if
+    // `enable_gpu` has been called, falling back to the CPU loop otherwise.
+    //
+    // The GPU path keeps its aliveness buffer resident on the device and
+    // steps it in place (no upload), only reading it back once afterwards
+    // to refresh `gpu_alive_cache` for `draw_cell` - one device round trip
+    // per frame instead of an upload, a round trip, *and* an O(width *
+    // height) CPU rebuild of `cells`.
+    fn update_cells(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        match &mut self.gpu_simulator {
+            Some(gpu) => {
+                gpu.step(device, queue);
+                self.gpu_alive_cache = gpu.download(device, queue);
+            }
+            None => self.update_cells_cpu(),
+        }
+    }
+
+    // Builds the row-major aliveness snapshot the GPU path works with, from
+    // the richer `cells` state. Only needed when (re)uploading - at
+    // `enable_gpu` time and after a resize - not on every step.
+    fn alive_snapshot(&self) -> Vec<u32> {
+        let mut alive = vec![0u32; (self.width * self.height) as usize];
+        for x in 0..self.width {
+            for y in 0..self.height {
+                alive[(y * self.width + x) as usize] =
+                    self.cells[self.mode.cell_index(x, y, self.width)].is_alive() as u32;
+            }
+        }
+        alive
+    }
+
+    // The inverse of `alive_snapshot`: re-derives the richer `Cell` state
+    // (age/fade) from a GPU aliveness snapshot. Only run when folding the
+    // device's state back into `cells`, i.e. in `disable_gpu`.
+    fn apply_alive_snapshot(&mut self, alive: &[u32]) {
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let id = self.mode.cell_index(x, y, self.width);
+                let alive_next = alive[(y * self.width + x) as usize] != 0;
+
+                self.cells[id] = match (alive_next, self.cells[id]) {
+                    (true, _) => Cell::Alive,
+                    (false, Cell::Alive) => Cell::Dead { since: 0 },
+                    (false, Cell::Dead { since }) => Cell::Dead {
+                        since: since.saturating_add(1).min(MAX_FADE),
+                    },
+                };
+            }
         }
     }
 
@@ -83,66 +458,76 @@ impl Grid {
     // 2 XXXXOX
     // 3 XXXXXX
     //XXXXXX XXXXOX XXXXXX
-    fn update_cells(&mut self) {
-        for x in 0..WIDTH {
-            for y in 0..HEIGHT {
-                let id = x + y * WIDTH;
-                let cell = &self.cells[id as usize];
-
-                // calculate neighbours of that cell
-                let neighbours_cell: [bool; 8] = [
-                    // From top-left to bottom-right
-                    self.cells
-                        .get((id - WIDTH - 1) as usize)
-                        .unwrap_or(&Cell::dead_cell())
-                        .is_alive,
-                    self.cells
-                        .get((id - WIDTH) as usize)
-                        .unwrap_or(&Cell::dead_cell())
-                        .is_alive,
-                    self.cells
-                        .get((id - WIDTH + 1) as usize)
-                        .unwrap_or(&Cell::dead_cell())
-                        .is_alive,
-                    self.cells
-                        .get((id - 1) as usize)
-                        .unwrap_or(&Cell::dead_cell())
-                        .is_alive,
-                    self.cells
-                        .get((id + 1) as usize)
-                        .unwrap_or(&Cell::dead_cell())
-                        .is_alive,
-                    self.cells
-                        .get((id + WIDTH - 1) as usize)
-                        .unwrap_or(&Cell::dead_cell())
-                        .is_alive,
-                    self.cells
-                        .get((id + WIDTH) as usize)
-                        .unwrap_or(&Cell::dead_cell())
-                        .is_alive,
-                    self.cells
-                        .get((id + WIDTH + 1) as usize)
-                        .unwrap_or(&Cell::dead_cell())
-                        .is_alive,
-                ];
-
-                let next_state = cell.process_next_state(neighbours_cell);
-                self.next_step_cells[id as usize] = next_state;
+    fn update_cells_cpu(&mut self) {
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let id = self.mode.cell_index(x, y, self.width);
+                let cell = &self.cells[id];
+
+                let mut neighbours_cell = [false; 8];
+                for (i, (dx, dy)) in Self::NEIGHBOUR_OFFSETS.iter().enumerate() {
+                    let (nx, ny) = self
+                        .mode
+                        .neighbour_coords(x, y, *dx, *dy, self.width, self.height);
+                    neighbours_cell[i] =
+                        self.cells[self.mode.cell_index(nx, ny, self.width)].is_alive();
+                }
+
+                let next_state = cell.process_next_state(neighbours_cell, &self.rule);
+                self.next_step_cells[id] = next_state;
             }
         }
         std::mem::swap(&mut self.next_step_cells, &mut self.cells);
     }
+
+    // Switches `update_cells` over to the `GpuSimulator` compute path,
+    // uploading the current pattern once and passing through the grid's own
+    // `mode`/`rule` so the device simulates the same rules the CPU loop
+    // would, rather than a hardcoded toroidal B3/S23.
+    fn enable_gpu(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let wrap = matches!(self.mode, BoundaryMode::Toroidal);
+        let gpu = GpuSimulator::new(
+            device,
+            queue,
+            self.width as u32,
+            self.height as u32,
+            wrap,
+            self.rule.birth_mask(),
+            self.rule.survival_mask(),
+        );
+
+        let alive = self.alive_snapshot();
+        gpu.upload(queue, &alive);
+        self.gpu_alive_cache = alive;
+        self.gpu_simulator = Some(gpu);
+    }
+
+    // Switches `update_cells` back to the CPU loop, folding the device's
+    // current state back into `cells` first so the pattern isn't lost.
+    fn disable_gpu(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if let Some(gpu) = self.gpu_simulator.take() {
+            let alive = gpu.download(device, queue);
+            self.apply_alive_snapshot(&alive);
+        }
+        self.gpu_alive_cache.clear();
+    }
 }
 
-fn main() -> Result<(), Error> {
-    env_logger::init();
+// Builds the window, the pixel buffer and the grid, then runs the event
+// loop. Shared by the native entry point and the wasm one below, since
+// building `Pixels` is async on wasm (adapter/device creation goes through
+// the browser) but can be driven synchronously on native.
+async fn run() -> Result<(), Error> {
     let event_loop = EventLoop::new();
+    let mut input = WinitInputHelper::new();
 
     // Creates the window that holds the game
     let window = {
-        let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
-        let scaled_size =
-            LogicalSize::new(WIDTH as f64 * SCALE_FACTOR, HEIGHT as f64 * SCALE_FACTOR);
+        let size = LogicalSize::new(INITIAL_WIDTH as f64, INITIAL_HEIGHT as f64);
+        let scaled_size = LogicalSize::new(
+            INITIAL_WIDTH as f64 * SCALE_FACTOR,
+            INITIAL_HEIGHT as f64 * SCALE_FACTOR,
+        );
 
         WindowBuilder::new()
             .with_title("Conway's Game of Life")
@@ -152,30 +537,255 @@ fn main() -> Result<(), Error> {
             .unwrap()
     };
 
+    #[cfg(target_arch = "wasm32")]
+    {
+        // There's no native window manager to size the window for us, so
+        // fill the browser window instead, and mount the canvas in the page.
+        let web_window = web_sys::window().expect("no global `window` exists");
+        let width = web_window.inner_width().unwrap().as_f64().unwrap();
+        let height = web_window.inner_height().unwrap().as_f64().unwrap();
+        window.set_inner_size(winit::dpi::LogicalSize::new(width, height));
+
+        web_window
+            .document()
+            .and_then(|document| document.body())
+            .and_then(|body| body.append_child(&window.canvas()).ok())
+            .expect("couldn't append canvas to document body");
+    }
+
     // A 2D pixels buffer
     let mut pixels = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        Pixels::new(WIDTH as u32, HEIGHT as u32, surface_texture)?
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            pixels::PixelsBuilder::new(INITIAL_WIDTH as u32, INITIAL_HEIGHT as u32, surface_texture)
+                .build_async()
+                .await?
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            Pixels::new(INITIAL_WIDTH as u32, INITIAL_HEIGHT as u32, surface_texture)?
+        }
     };
 
     // Create a grid full of ded cells
-    let mut grid = Grid::get_randomized_grid();
+    let mut grid = Grid::get_randomized_grid(
+        INITIAL_WIDTH,
+        INITIAL_HEIGHT,
+        BoundaryMode::Bounded,
+        Rule::default(),
+    );
 
     // Set clear color to red.
     pixels.clear_color(Color::BLACK);
 
+    // Freezes `update_cells` so the grid can be edited; `Space` still
+    // advances a single generation while paused.
+    let mut paused = false;
+    // Grid coordinates of the mouse on the last processed frame, so a
+    // drag can be turned into a line instead of a trail of isolated dots.
+    let mut last_drawn_cell: Option<(i32, i32)> = None;
+    // `G` toggles the GPU compute path on and off at runtime. Native-only
+    // for now - see the wasm32 cfg-gate on the key handler below.
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut use_gpu = false;
+
     event_loop.run(move |event, _, control_flow| {
-        // Clear the pixel buffer
-        let frame = pixels.frame_mut();
+        if let Event::RedrawRequested(_) = event {
+            let frame = pixels.frame_mut();
+            grid.draw_cell(frame);
 
-        grid.draw_cell(frame);
+            if pixels.render().is_err() {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+        }
+
+        if input.update(&event) {
+            if input.close_requested() {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+
+            if let Some(size) = input.window_resized() {
+                pixels
+                    .resize_surface(size.width, size.height)
+                    .expect("failed to resize the pixels surface");
+
+                let new_width = ((size.width as f64) / SCALE_FACTOR).round().max(1.0) as i32;
+                let new_height = ((size.height as f64) / SCALE_FACTOR).round().max(1.0) as i32;
 
-        // Draw it to the `SurfaceTexture`
-        pixels.render().unwrap(); // todo handle error
-        window.request_redraw();
+                pixels
+                    .resize_buffer(new_width as u32, new_height as u32)
+                    .expect("failed to resize the pixels buffer");
 
-        grid.update_cells();
+                grid.resize(new_width, new_height, pixels.device(), pixels.queue());
+            }
+
+            if input.key_pressed(VirtualKeyCode::P) {
+                paused = !paused;
+            }
+
+            // `GpuSimulator::download`'s readback blocks on `device.poll`,
+            // which never resolves on the WebGPU/WebGL backend - there's no
+            // blocking poll on the browser's single JS thread, so `map_async`
+            // would never get a chance to run its callback. Keep the toggle
+            // native-only until that readback is made properly async.
+            #[cfg(not(target_arch = "wasm32"))]
+            if input.key_pressed(VirtualKeyCode::G) {
+                use_gpu = !use_gpu;
+                if use_gpu {
+                    grid.enable_gpu(pixels.device(), pixels.queue());
+                } else {
+                    grid.disable_gpu(pixels.device(), pixels.queue());
+                }
+            }
 
+            if let Some((x, y)) = input.mouse() {
+                let (cell_x, cell_y) = pixels
+                    .window_pos_to_pixel((x, y))
+                    .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
+                let current_cell = (cell_x as i32, cell_y as i32);
+
+                if input.mouse_pressed(0) {
+                    grid.toggle_cell(current_cell.0, current_cell.1, pixels.queue());
+                    last_drawn_cell = Some(current_cell);
+                } else if input.mouse_held(0) {
+                    let from = last_drawn_cell.unwrap_or(current_cell);
+                    // Only paint once the cursor has left the last cell, so
+                    // holding a click in place doesn't undo its toggle.
+                    if from != current_cell {
+                        grid.draw_line(from, current_cell, pixels.queue());
+                    }
+                    last_drawn_cell = Some(current_cell);
+                }
+            }
+
+            if input.mouse_released(0) {
+                last_drawn_cell = None;
+            }
+
+            if paused {
+                if input.key_pressed(VirtualKeyCode::Space) {
+                    grid.update_cells(pixels.device(), pixels.queue());
+                }
+            } else {
+                grid.update_cells(pixels.device(), pixels.queue());
+            }
+
+            window.request_redraw();
+        }
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> Result<(), Error> {
+    env_logger::init();
+    pollster::block_on(run())
+}
+
+// wasm has no native event loop to block on, so `run()` is instead spawned
+// onto the browser's own loop as soon as the module is instantiated.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen(start)]
+pub fn main_wasm() {
+    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+    console_log::init_with_level(log::Level::Warn).expect("failed to init console_log");
+
+    wasm_bindgen_futures::spawn_local(async {
+        run().await.expect("run() failed");
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_classic_life() {
+        let rule = Rule::parse("B3/S23").unwrap();
+        assert_eq!(rule.birth, [false, false, false, true, false, false, false, false, false]);
+        assert_eq!(
+            rule.survival,
+            [false, false, true, true, false, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn parses_multi_digit_rules() {
+        let rule = Rule::parse("B36/S23").unwrap();
+        assert!(rule.birth[3] && rule.birth[6]);
+        assert_eq!(rule.birth.iter().filter(|b| **b).count(), 2);
+    }
+
+    #[test]
+    fn parses_empty_half() {
+        // "Seeds": born with 2 neighbours, nothing ever survives.
+        let rule = Rule::parse("B2/S").unwrap();
+        assert!(rule.birth[2]);
+        assert!(rule.survival.iter().all(|s| !s));
+    }
+
+    #[test]
+    fn rejects_missing_separator() {
+        assert!(Rule::parse("B3S23").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_b_prefix() {
+        assert!(Rule::parse("3/S23").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_s_prefix() {
+        assert!(Rule::parse("B3/23").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_neighbour_count() {
+        assert!(Rule::parse("B9/S23").is_err());
+    }
+
+    #[test]
+    fn masks_match_birth_and_survival_tables() {
+        let rule = Rule::parse("B3/S23").unwrap();
+        assert_eq!(rule.birth_mask(), 1 << 3);
+        assert_eq!(rule.survival_mask(), (1 << 2) | (1 << 3));
+    }
+
+    #[test]
+    fn bounded_cell_index_accounts_for_the_halo() {
+        // Width 5 means each row is 7 wide once the one-cell halo is added
+        // on both sides, and (0, 0) sits one cell in from the top-left.
+        assert_eq!(BoundaryMode::Bounded.cell_index(0, 0, 5), 8);
+        assert_eq!(BoundaryMode::Bounded.cell_index(-1, -1, 5), 0);
+    }
+
+    #[test]
+    fn toroidal_cell_index_is_plain_row_major() {
+        assert_eq!(BoundaryMode::Toroidal.cell_index(2, 3, 5), 2 + 3 * 5);
+    }
+
+    #[test]
+    fn bounded_neighbour_coords_pass_through_unclamped() {
+        // `Bounded` relies on the halo rather than wrapping, so a neighbour
+        // one step past the edge should come back as an out-of-range
+        // coordinate (which `cell_index` then maps into the dead halo).
+        let (x, y) = BoundaryMode::Bounded.neighbour_coords(0, 0, -1, -1, 5, 5);
+        assert_eq!((x, y), (-1, -1));
+    }
+
+    #[test]
+    fn toroidal_neighbour_coords_wrap_at_both_edges() {
+        assert_eq!(
+            BoundaryMode::Toroidal.neighbour_coords(0, 0, -1, -1, 5, 5),
+            (4, 4)
+        );
+        assert_eq!(
+            BoundaryMode::Toroidal.neighbour_coords(4, 4, 1, 1, 5, 5),
+            (0, 0)
+        );
+    }
+}